@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use octocrab::{Octocrab, OctocrabBuilder};
+use tracing::info;
+
+use super::{Backend, RemoteFile};
+use crate::error::Error;
+use crate::README_FILE;
+
+/// Syncs files against a single GitHub gist
+pub struct GistBackend {
+    gist_id: String,
+    github_access_token: String,
+}
+
+impl GistBackend {
+    pub fn new(gist_id: String, github_access_token: String) -> Self {
+        Self {
+            gist_id,
+            github_access_token,
+        }
+    }
+
+    fn octocrab(&self) -> Result<Octocrab, Error> {
+        Ok(OctocrabBuilder::new()
+            .user_access_token(self.github_access_token.clone())
+            .build()?)
+    }
+}
+
+#[async_trait]
+impl Backend for GistBackend {
+    async fn list(&self) -> Result<Vec<RemoteFile>, Error> {
+        self.octocrab()?
+            .gists()
+            .get(&self.gist_id)
+            .await?
+            .files
+            .into_iter()
+            .filter(|(file_name, _)| file_name.as_str() != README_FILE)
+            .map(|(name, gist_file)| {
+                gist_file
+                    .content
+                    .ok_or_else(|| Error::MissingRemoteFileContent { name: name.clone() })
+                    .map(|content| RemoteFile { name, content })
+            })
+            .collect()
+    }
+
+    async fn upsert(&self, files: Vec<RemoteFile>) -> Result<(), Error> {
+        let gist = self.octocrab()?.gists().update(self.gist_id.clone());
+        let gist = files
+            .into_iter()
+            .fold(gist, |gist, file| gist.file(file.name).with_content(file.content))
+            .send()
+            .await?;
+        info!("uploaded to {}", gist.html_url);
+        Ok(())
+    }
+
+    async fn delete(&self, file_names: Vec<String>) -> Result<(), Error> {
+        if file_names.is_empty() {
+            return Ok(());
+        }
+        file_names
+            .into_iter()
+            .fold(self.octocrab()?.gists().update(self.gist_id.clone()), |gist, name| {
+                gist.file(name).delete()
+            })
+            .send()
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use git2::build::CheckoutBuilder;
+use git2::{Cred, PushOptions, RemoteCallbacks, Repository, Signature};
+use tracing::info;
+
+use super::{Backend, RemoteFile};
+use crate::error::Error;
+use crate::README_FILE;
+
+/// Syncs files against a subdirectory of a plain Git repository, committing and pushing on
+/// every write so a dotfiles repo stays in sync through the user's normal Git workflow.
+pub struct GitBackend {
+    repo_url: String,
+    branch: String,
+    local_checkout: PathBuf,
+    subdirectory: PathBuf,
+    commit_author_name: String,
+    commit_author_email: String,
+}
+
+impl GitBackend {
+    pub fn new(
+        repo_url: String,
+        branch: String,
+        local_checkout: PathBuf,
+        subdirectory: PathBuf,
+        commit_author_name: String,
+        commit_author_email: String,
+    ) -> Self {
+        Self {
+            repo_url,
+            branch,
+            local_checkout,
+            subdirectory,
+            commit_author_name,
+            commit_author_email,
+        }
+    }
+
+    fn open_or_clone(&self) -> Result<Repository, Error> {
+        let repo = match Repository::open(&self.local_checkout) {
+            Ok(repo) => repo,
+            Err(_) => Repository::clone(&self.repo_url, &self.local_checkout)?,
+        };
+        self.checkout_branch(&repo)?;
+        Ok(repo)
+    }
+
+    /// Makes `self.branch` the checked-out branch, so `commit_and_push` commits (and the
+    /// push refspec targets) the branch the caller asked for instead of whatever branch the
+    /// clone happened to default to.
+    ///
+    /// Creates the branch locally, starting from the matching remote branch's commit if one
+    /// exists, or from `HEAD` otherwise; no upstream is configured for it. If the repository has
+    /// no commits yet, points `HEAD` at the still-unborn branch; `commit_and_push`'s first commit
+    /// creates it via `HEAD`.
+    fn checkout_branch(&self, repo: &Repository) -> Result<(), Error> {
+        let branch_ref = format!("refs/heads/{}", self.branch);
+        if repo.find_reference(&branch_ref).is_err() {
+            let remote_ref = format!("refs/remotes/origin/{}", self.branch);
+            let base_commit = repo
+                .find_reference(&remote_ref)
+                .or_else(|_| repo.head())
+                .and_then(|reference| reference.peel_to_commit());
+            if let Ok(commit) = base_commit {
+                repo.branch(&self.branch, &commit, false)?;
+            }
+        }
+
+        let branch_exists = repo.find_reference(&branch_ref).is_ok();
+        repo.set_head(&branch_ref)?;
+        if branch_exists {
+            repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        }
+        Ok(())
+    }
+
+    fn synced_dir(&self, repo: &Repository) -> PathBuf {
+        repo.workdir()
+            .expect("git backend requires a non-bare repository")
+            .join(&self.subdirectory)
+    }
+
+    fn commit_and_push(&self, repo: &Repository, message: &str) -> Result<(), Error> {
+        let mut index = repo.index()?;
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = Signature::now(&self.commit_author_name, &self.commit_author_email)?;
+        let parents = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let date = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("{message} ({date})"),
+            &tree,
+            &parents.iter().collect::<Vec<_>>(),
+        )?;
+
+        let mut remote = repo.find_remote("origin")?;
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|_url, username_from_url, _allowed_types| {
+            Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+        });
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", self.branch);
+        remote.push(&[refspec], Some(&mut push_options))?;
+        info!("pushed \"{message}\" to {}", self.repo_url);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Backend for GitBackend {
+    async fn list(&self) -> Result<Vec<RemoteFile>, Error> {
+        let repo = self.open_or_clone()?;
+        let dir = self.synced_dir(&repo);
+        fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_file())
+            .filter(|entry| entry.file_name() != README_FILE)
+            .map(|entry| {
+                let path = entry.path();
+                fs::read_to_string(&path)
+                    .map_err(|source| Error::Read { path, source })
+                    .map(|content| RemoteFile {
+                        name: entry.file_name().to_string_lossy().into_owned(),
+                        content,
+                    })
+            })
+            .collect()
+    }
+
+    async fn upsert(&self, files: Vec<RemoteFile>) -> Result<(), Error> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let repo = self.open_or_clone()?;
+        let dir = self.synced_dir(&repo);
+        fs::create_dir_all(&dir).map_err(|source| Error::Write {
+            path: dir.clone(),
+            source,
+        })?;
+        let num_files = files.len();
+        for file in files {
+            let path = dir.join(&file.name);
+            fs::write(&path, file.content).map_err(|source| Error::Write { path, source })?;
+        }
+        self.commit_and_push(&repo, &format!("sync {num_files} cfg file(s)"))
+    }
+
+    async fn delete(&self, file_names: Vec<String>) -> Result<(), Error> {
+        if file_names.is_empty() {
+            return Ok(());
+        }
+        let repo = self.open_or_clone()?;
+        let dir = self.synced_dir(&repo);
+        let num_files = file_names.len();
+        for file_name in file_names {
+            let _ = fs::remove_file(dir.join(file_name));
+        }
+        self.commit_and_push(&repo, &format!("remove {num_files} cfg file(s)"))
+    }
+}
@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use clap::ValueEnum;
+
+use crate::error::Error;
+
+mod git;
+mod gist;
+
+pub use git::GitBackend;
+pub use gist::GistBackend;
+
+/// A single file as seen on the remote side of a sync (a Gist file, a file in a Git repo, etc.)
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub name: String,
+    pub content: String,
+}
+
+/// A place `push`/`pull` can sync the compiled config tree to and from
+#[async_trait]
+pub trait Backend {
+    /// Lists every file currently stored on the remote
+    async fn list(&self) -> Result<Vec<RemoteFile>, Error>;
+    /// Creates or overwrites the given files on the remote
+    async fn upsert(&self, files: Vec<RemoteFile>) -> Result<(), Error>;
+    /// Removes the named files from the remote
+    async fn delete(&self, file_names: Vec<String>) -> Result<(), Error>;
+}
+
+/// Which kind of remote `push`/`pull` should sync against
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BackendKind {
+    /// A single GitHub gist, identified by `--gist-id`
+    Gist,
+    /// A plain Git repository, identified by `--repo-url`
+    Git,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gist => write!(f, "gist"),
+            Self::Git => write!(f, "git"),
+        }
+    }
+}
@@ -1,67 +1,165 @@
-use std::fs::File;
-use std::io::Write;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use clap::Args;
 use regex::Regex;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+use crate::config::Profile;
+use crate::error::Error;
 
 #[derive(Args, Debug, Clone)]
 pub struct CompileOptions {
-    /// The `./cfg` directory to run against, used to get relative paths from exec calls to concatenate the files
+    /// The `./cfg` directory to run against, used to get relative paths from exec calls to concatenate the files. Falls back to the active profile's `cfg_dir` if omitted
     #[arg()]
-    cfg_dir: PathBuf,
-    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files
+    cfg_dir: Option<PathBuf>,
+    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files. Falls back to the active profile's `root_file` if omitted
     #[arg()]
-    root_file: PathBuf,
+    root_file: Option<PathBuf>,
     /// Whether or not to actually write the file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     dry_run: bool,
 }
 
+impl CompileOptions {
+    pub(crate) fn new(cfg_dir: PathBuf, root_file: PathBuf, dry_run: bool) -> Self {
+        Self {
+            cfg_dir: Some(cfg_dir),
+            root_file: Some(root_file),
+            dry_run,
+        }
+    }
+
+    fn resolve(self, profile: &Profile) -> Result<(PathBuf, PathBuf, bool), Error> {
+        let cfg_dir = self.cfg_dir.or_else(|| profile.cfg_dir.clone()).ok_or_else(|| {
+            Error::MissingOption("cfg_dir must be given on the command line or in the active profile".to_owned())
+        })?;
+        let root_file = self.root_file.or_else(|| profile.root_file.clone()).ok_or_else(|| {
+            Error::MissingOption("root_file must be given on the command line or in the active profile".to_owned())
+        })?;
+        Ok((cfg_dir, root_file, self.dry_run))
+    }
+}
+
 fn get_exec_file_path(cfg_dir_path: &Path, exec_file_path: &str) -> PathBuf {
     cfg_dir_path.join(exec_file_path.as_str().to_owned() + ".cfg")
 }
 
-fn compile(cfg_dir_path: &Path, path: &Path) -> String {
+fn compile(cfg_dir_path: &Path, path: &Path) -> Result<String, Error> {
+    let mut exec_stack = HashSet::new();
+    let mut emitted = HashSet::new();
+    compile_included(cfg_dir_path, path, &mut exec_stack, &mut emitted)
+}
+
+/// Recursively inlines `path` and its `exec`ed files, guarding against cycles (a file that
+/// transitively execs itself) and diamonds (a file reached by more than one exec path).
+///
+/// `exec_stack` holds the canonicalized paths currently being compiled, to detect cycles.
+/// `emitted` holds every canonicalized path already inlined, so diamonds are only inlined once.
+fn compile_included(
+    cfg_dir_path: &Path,
+    path: &Path,
+    exec_stack: &mut HashSet<PathBuf>,
+    emitted: &mut HashSet<PathBuf>,
+) -> Result<String, Error> {
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if exec_stack.contains(&canonical_path) {
+        warn!("skipping circular exec of {}", path.display());
+        return Ok(format!("// skipped circular exec: {}", path.display()));
+    }
+    if !emitted.insert(canonical_path.clone()) {
+        debug!("skipping duplicate exec of {}", path.display());
+        return Ok(format!("// already included: {}", path.display()));
+    }
+
     debug!("compiling {} in compiled config", path.display());
+    exec_stack.insert(canonical_path.clone());
     let regex = Regex::new(r#"^exec "([^"]+)"|(.+)"#).unwrap();
-    let file_contents = crate::read_to_string(path);
-
-    file_contents
-        .lines()
-        .map(|line| {
-            regex
-                .captures(line)
-                .and_then(|captures| captures.get(1))
-                .map_or_else(
-                    || line.to_owned(),
-                    |exec_file_path| {
-                        compile(cfg_dir_path, &get_exec_file_path(cfg_dir_path, exec_file_path.as_str()))
-                    },
-                )
-        })
-        .collect::<Vec<String>>()
-        .join("\n")
+    let file_contents = crate::read_to_string(path)?;
+
+    let mut compiled_lines = Vec::with_capacity(file_contents.lines().count());
+    for line in file_contents.lines() {
+        let compiled_line = match regex.captures(line).and_then(|captures| captures.get(1)) {
+            Some(exec_file_path) => compile_included(
+                cfg_dir_path,
+                &get_exec_file_path(cfg_dir_path, exec_file_path.as_str()),
+                exec_stack,
+                emitted,
+            )?,
+            None => line.to_owned(),
+        };
+        compiled_lines.push(compiled_line);
+    }
+    exec_stack.remove(&canonical_path);
+    Ok(compiled_lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a fresh, empty temp directory for a single test, scoped by test name and pid so
+    /// parallel test runs don't collide.
+    fn temp_cfg_dir(test_name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cs-config-manager-test-compile-{test_name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create temp cfg dir");
+        dir
+    }
+
+    #[test]
+    fn marks_circular_execs_with_a_comment() {
+        let dir = temp_cfg_dir("cycle");
+        std::fs::write(dir.join("a.cfg"), "exec \"b\"\n").unwrap();
+        std::fs::write(dir.join("b.cfg"), "exec \"a\"\n").unwrap();
+
+        let compiled = compile(&dir, &dir.join("a.cfg")).expect("should not error");
+
+        assert!(
+            compiled.contains("// skipped circular exec:"),
+            "expected a circular exec marker in: {compiled}"
+        );
+    }
+
+    #[test]
+    fn marks_diamond_dependency_as_already_included() {
+        let dir = temp_cfg_dir("diamond");
+        std::fs::write(dir.join("root.cfg"), "exec \"left\"\nexec \"right\"\n").unwrap();
+        std::fs::write(dir.join("left.cfg"), "exec \"shared\"\n").unwrap();
+        std::fs::write(dir.join("right.cfg"), "exec \"shared\"\n").unwrap();
+        std::fs::write(dir.join("shared.cfg"), "bind e use\n").unwrap();
+
+        let compiled = compile(&dir, &dir.join("root.cfg")).expect("should not error");
+
+        assert!(
+            compiled.contains("// already included:"),
+            "expected an already-included marker in: {compiled}"
+        );
+    }
 }
 
-pub fn compile_and_write(options: CompileOptions) {
-    let root_cfg = options.cfg_dir.join(options.root_file);
-    let compiled = compile(&options.cfg_dir, &root_cfg);
+pub fn compile_and_write(options: CompileOptions, profile: &Profile) -> Result<(), Error> {
+    let (cfg_dir, root_file, dry_run) = options.resolve(profile)?;
+    let root_cfg = cfg_dir.join(root_file);
+    let compiled = compile(&cfg_dir, &root_cfg)?;
     let output_path = root_cfg.parent().unwrap().join("compiled.cfg");
     let date = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let compiled = format!("// Compiled on {date}\n\n{compiled}");
-    if options.dry_run {
+    if dry_run {
         info!(
             "skipping writing compiled {}B to {} due to --dry-run",
             compiled.as_bytes().len(),
             output_path.display()
         );
     } else {
-        let written_bytes = File::create(&output_path)
-            .unwrap()
-            .write(compiled.as_bytes())
-            .unwrap();
-        info!("compiled {written_bytes}B to {}", output_path.display());
+        std::fs::write(&output_path, compiled.as_bytes()).map_err(|source| Error::Write {
+            path: output_path.clone(),
+            source,
+        })?;
+        info!("compiled {}B to {}", compiled.len(), output_path.display());
     }
+    Ok(())
 }
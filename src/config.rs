@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// The config file name looked for in the working directory when `--config` isn't given
+pub const DEFAULT_CONFIG_FILE: &str = "cs-config.toml";
+
+/// Where an access token value comes from, so it doesn't have to be checked in as plaintext
+///
+/// `untagged` on the enum itself can't be combined with `deny_unknown_fields` (it has no effect
+/// there, since untagged dispatch just falls through to the next variant on any mismatch), so the
+/// `env` table is its own struct that carries `deny_unknown_fields`, so a typo'd key in it is
+/// rejected instead of silently ignored. The error still reads as no variant of `TokenSource`
+/// matching, since untagged dispatch does not surface the inner error.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TokenSource {
+    /// Read the token directly out of the config file
+    Plain(String),
+    /// Read the token from the named environment variable at runtime
+    Env(EnvTokenSource),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvTokenSource {
+    #[serde(rename = "env")]
+    env_var: String,
+}
+
+impl TokenSource {
+    pub fn resolve(&self) -> Result<String, Error> {
+        match self {
+            Self::Plain(token) => Ok(token.clone()),
+            Self::Env(EnvTokenSource { env_var }) => {
+                std::env::var(env_var).map_err(|_| Error::MissingEnvVar(env_var.clone()))
+            }
+        }
+    }
+}
+
+/// One named set of defaults for `cfg_dir`, `root_file`, and remote sync options
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub cfg_dir: Option<PathBuf>,
+    pub root_file: Option<PathBuf>,
+    pub gist_id: Option<String>,
+    pub access_token: Option<TokenSource>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Used when `--profile` isn't passed
+    #[serde(default)]
+    pub default: Profile,
+    #[serde(default, rename = "profile")]
+    pub profiles: HashMap<String, Profile>,
+}
+
+impl Config {
+    /// Loads and parses the config file at `path`, if it exists. Returns the empty (all-`None`)
+    /// config when `path` is absent so CLI-only invocations keep working without a config file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file_contents = crate::read_to_string(path)?;
+        toml::from_str(&file_contents).map_err(|source| Error::ConfigParse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Looks up the named profile, falling back to the top-level default profile when `name` is `None`
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, Error> {
+        match name {
+            None => Ok(&self.default),
+            Some(name) => self
+                .profiles
+                .get(name)
+                .ok_or_else(|| Error::UnknownProfile(name.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::TokenSource;
+
+    #[derive(Deserialize)]
+    struct Wrapper {
+        access_token: TokenSource,
+    }
+
+    #[test]
+    fn plain_token_source_is_a_bare_string() {
+        let wrapper: Wrapper =
+            toml::from_str("access_token = \"abc123\"").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(wrapper.access_token.resolve().unwrap(), "abc123");
+    }
+
+    #[test]
+    fn env_token_source_reads_the_named_variable() {
+        let wrapper: Wrapper =
+            toml::from_str("access_token = { env = \"CS_CONFIG_MANAGER_TEST_TOKEN\" }")
+                .unwrap_or_else(|err| panic!("{err}"));
+        std::env::set_var("CS_CONFIG_MANAGER_TEST_TOKEN", "xyz789");
+        assert_eq!(wrapper.access_token.resolve().unwrap(), "xyz789");
+    }
+
+    #[test]
+    fn env_token_source_errors_when_unset() {
+        let wrapper: Wrapper =
+            toml::from_str("access_token = { env = \"CS_CONFIG_MANAGER_TEST_TOKEN_UNSET\" }")
+                .unwrap_or_else(|err| panic!("{err}"));
+        std::env::remove_var("CS_CONFIG_MANAGER_TEST_TOKEN_UNSET");
+        assert!(wrapper.access_token.resolve().is_err());
+    }
+
+    #[test]
+    fn env_token_source_rejects_unknown_keys() {
+        let result = toml::from_str::<Wrapper>("access_token = { envv = \"CS_CONFIG_MANAGER_TEST_TOKEN\" }");
+        assert!(result.is_err(), "a typo'd key should be rejected, not silently ignored");
+    }
+}
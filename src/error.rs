@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// The crate-wide error type, returned by every fallible command so `main` can report a concise
+/// message and exit non-zero instead of panicking.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("could not read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("could not write {path}: {source}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("could not parse config file {path}: {source}")]
+    ConfigParse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("GitHub API request failed: {0}")]
+    GitHubApi(#[from] octocrab::Error),
+    #[error("git operation failed: {0}")]
+    Git(#[from] git2::Error),
+    #[error("file watcher error: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("remote file \"{name}\" has no content")]
+    MissingRemoteFileContent { name: String },
+    #[error("remote file \"{name}\" has a malformed header line")]
+    MalformedHeader { name: String },
+    #[error("environment variable {0} is not set")]
+    MissingEnvVar(String),
+    #[error("no profile named \"{0}\" found in config file")]
+    UnknownProfile(String),
+    #[error("{0}")]
+    MissingOption(String),
+}
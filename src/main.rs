@@ -1,32 +1,46 @@
 #![deny(clippy::pedantic, clippy::nursery)]
 #![allow(clippy::module_name_repetitions, clippy::significant_drop_tightening)]
 
-use std::fs::File;
-use std::io::Read;
-use std::iter::once;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::OnceLock;
 
 use clap::{Parser, Subcommand};
 use compile::CompileOptions;
+use config::Config;
+use error::Error;
 use pull::PullOptions;
 use push::PushOptions;
 use regex::Regex;
-use tracing::Level;
+use status::StatusOptions;
+use tracing::{debug, warn, Level};
 use tracing_subscriber::filter::{FilterExt, LevelFilter, Targets};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{registry, Layer};
+use watch::WatchOptions;
 
+mod backend;
 mod compile;
+mod config;
+mod error;
 mod pull;
 mod push;
+mod status;
+mod watch;
 
 #[derive(Debug, Clone, Parser)]
 #[command(author, version, about)]
 struct CsConfigManagerArgs {
     #[command(subcommand)]
     command: CsConfigManagerCommand,
+    /// Path to the TOML config file holding named profiles, defaults to `cs-config.toml` in the working directory
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// The named `[profile.<name>]` to use, defaults to the top-level default profile
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -34,16 +48,17 @@ pub enum CsConfigManagerCommand {
     Compile(CompileOptions),
     Push(PushOptions),
     Pull(PullOptions),
+    Status(StatusOptions),
+    Watch(WatchOptions),
 }
 
 pub const README_FILE: &str = "README.md";
 
-fn read_to_string(full_path: &Path) -> String {
-    let mut file_contents = String::with_capacity(1024);
-    let _ = File::open(full_path)
-        .and_then(|mut file| file.read_to_string(&mut file_contents))
-        .unwrap();
-    file_contents
+fn read_to_string(full_path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(full_path).map_err(|source| Error::Read {
+        path: full_path.to_path_buf(),
+        source,
+    })
 }
 
 #[derive(Debug)]
@@ -53,54 +68,147 @@ struct IncludedFile {
 }
 
 impl IncludedFile {
-    fn get_formatted_content(&self) -> impl Into<String> {
+    /// Lossily converts paths that aren't valid UTF-8, matching how remote file names are
+    /// already built in the git backend (`backend::git::GitBackend::list`)
+    fn get_formatted_content(&self) -> String {
         format!(
             "// {}\n{}",
-            self.relative_file_path.to_str().unwrap(),
+            self.relative_file_path.to_string_lossy(),
             self.file_contents,
         )
     }
-}
 
-impl IncludedFile {
-    fn get_file_name(&self) -> &str {
+    fn get_file_name(&self) -> String {
         self.relative_file_path
             .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
+            .unwrap_or(self.relative_file_path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
     }
 }
 
-fn get_included_files(cfg_dir_path: &Path, path: &Path) -> Vec<IncludedFile> {
+fn get_included_files(cfg_dir_path: &Path, path: &Path) -> Result<Vec<IncludedFile>, Error> {
+    let mut exec_stack = HashSet::new();
+    let mut emitted = HashSet::new();
+    get_included_files_inner(cfg_dir_path, path, &mut exec_stack, &mut emitted)
+}
+
+/// Recursively collects `path` and its `exec`ed files, guarding against cycles (a file that
+/// transitively execs itself) and diamonds (a file reached by more than one exec path).
+///
+/// `exec_stack` holds the canonicalized paths currently being walked, to detect cycles.
+/// `emitted` holds every canonicalized path already collected, so diamonds are only included once.
+fn get_included_files_inner(
+    cfg_dir_path: &Path,
+    path: &Path,
+    exec_stack: &mut HashSet<PathBuf>,
+    emitted: &mut HashSet<PathBuf>,
+) -> Result<Vec<IncludedFile>, Error> {
     static EXEC_REGEX: OnceLock<Regex> = OnceLock::new();
     let exec_regex = EXEC_REGEX.get_or_init(|| Regex::new(r#"^exec "([^"]+)"|(.+)"#).unwrap());
 
     let full_path = cfg_dir_path.join(path);
-    let file_contents = read_to_string(&full_path);
+    let canonical_path = full_path.canonicalize().unwrap_or_else(|_| full_path.clone());
+    if exec_stack.contains(&canonical_path) {
+        warn!("skipping circular exec of {}", path.display());
+        return Ok(Vec::new());
+    }
+    if !emitted.insert(canonical_path.clone()) {
+        debug!("skipping duplicate exec of {}", path.display());
+        return Ok(Vec::new());
+    }
 
-    once(IncludedFile {
+    exec_stack.insert(canonical_path.clone());
+    let file_contents = read_to_string(&full_path)?;
+
+    let mut included = vec![IncludedFile {
         relative_file_path: path.to_path_buf(),
-        file_contents: read_to_string(&full_path),
-    })
-    .chain(
-        file_contents
-            .lines()
-            .filter_map(|line| {
-                exec_regex
-                    .captures(line)
-                    .and_then(|captures| captures.get(1))
-            })
-            .flat_map(|exec_file_path| {
-                let next_path = exec_file_path.as_str().to_owned() + ".cfg";
-                get_included_files(cfg_dir_path, &PathBuf::from(next_path))
-            }),
-    )
-    .collect()
+        file_contents: file_contents.clone(),
+    }];
+    for exec_file_path in file_contents
+        .lines()
+        .filter_map(|line| exec_regex.captures(line).and_then(|captures| captures.get(1)))
+    {
+        let next_path = exec_file_path.as_str().to_owned() + ".cfg";
+        included.extend(get_included_files_inner(
+            cfg_dir_path,
+            &PathBuf::from(next_path),
+            exec_stack,
+            emitted,
+        )?);
+    }
+    exec_stack.remove(&canonical_path);
+    Ok(included)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a fresh, empty temp directory for a single test, scoped by test name and pid so
+    /// parallel test runs don't collide.
+    fn temp_cfg_dir(test_name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cs-config-manager-test-{test_name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create temp cfg dir");
+        dir
+    }
+
+    #[test]
+    fn breaks_circular_execs() {
+        let dir = temp_cfg_dir("cycle");
+        std::fs::write(dir.join("a.cfg"), "exec \"b\"\n").unwrap();
+        std::fs::write(dir.join("b.cfg"), "exec \"a\"\n").unwrap();
+
+        let included = get_included_files(&dir, Path::new("a.cfg")).expect("should not error");
+
+        // a.cfg and b.cfg are each included once; the cyclic re-entry into a.cfg is skipped
+        assert_eq!(included.len(), 2);
+    }
+
+    #[test]
+    fn includes_diamond_dependency_once() {
+        let dir = temp_cfg_dir("diamond");
+        std::fs::write(dir.join("root.cfg"), "exec \"left\"\nexec \"right\"\n").unwrap();
+        std::fs::write(dir.join("left.cfg"), "exec \"shared\"\n").unwrap();
+        std::fs::write(dir.join("right.cfg"), "exec \"shared\"\n").unwrap();
+        std::fs::write(dir.join("shared.cfg"), "bind e use\n").unwrap();
+
+        let included = get_included_files(&dir, Path::new("root.cfg")).expect("should not error");
+
+        assert_eq!(included.len(), 4);
+        let shared_count = included
+            .iter()
+            .filter(|file| file.relative_file_path == Path::new("shared.cfg"))
+            .count();
+        assert_eq!(shared_count, 1, "a diamond dependency should only be included once");
+    }
+}
+
+async fn run() -> Result<(), Error> {
+    let CsConfigManagerArgs {
+        command,
+        config,
+        profile,
+    } = CsConfigManagerArgs::parse();
+    let config_path = config.unwrap_or_else(|| PathBuf::from(config::DEFAULT_CONFIG_FILE));
+    let config = Config::load(&config_path)?;
+    let profile = config.profile(profile.as_deref())?;
+
+    match command {
+        CsConfigManagerCommand::Compile(options) => compile::compile_and_write(options, profile),
+        CsConfigManagerCommand::Push(options) => push::push_config(options, profile).await,
+        CsConfigManagerCommand::Pull(options) => pull::pull_config(options, profile).await,
+        CsConfigManagerCommand::Status(options) => status::status_config(options, profile).await,
+        CsConfigManagerCommand::Watch(options) => watch::watch_config(options, profile).await,
+    }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let stdout_subscriber = tracing_subscriber::fmt::layer()
         .without_time()
         .with_target(false)
@@ -111,10 +219,9 @@ async fn main() {
         );
     registry().with(stdout_subscriber).init();
 
-    let CsConfigManagerArgs { command } = CsConfigManagerArgs::parse();
-    match command {
-        CsConfigManagerCommand::Compile(options) => compile::compile_and_write(options),
-        CsConfigManagerCommand::Push(options) => push::push_config(options).await,
-        CsConfigManagerCommand::Pull(options) => pull::pull_config(options).await,
+    if let Err(err) = run().await {
+        tracing::error!("{err}");
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
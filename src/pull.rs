@@ -1,7 +1,8 @@
-use crate::README_FILE;
+use crate::backend::{Backend, BackendKind, GistBackend, GitBackend};
+use crate::config::{Profile, TokenSource};
+use crate::error::Error;
 use clap::Args;
 use futures::future::join_all;
-use octocrab::OctocrabBuilder;
 use std::path::PathBuf;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
@@ -9,15 +10,30 @@ use tracing::info;
 
 #[derive(Args, Debug, Clone)]
 pub struct PullOptions {
-    /// The `./cfg` directory to run against, used to get relative paths from exec calls to include with the files
+    /// The `./cfg` directory to run against, used to get relative paths from exec calls to include with the files. Falls back to the active profile's `cfg_dir` if omitted
     #[arg(value_name = "CFG_DIR", value_hint = clap::ValueHint::DirPath)]
-    cfg_dir: PathBuf,
-    /// The gist id to publish to
-    #[arg(long, required = true)]
-    gist_id: String,
-    /// The github access token to authenticate using
-    #[arg(short = 't', long = "access-token", required = true)]
-    github_access_token: String,
+    cfg_dir: Option<PathBuf>,
+    /// Which kind of remote to pull from
+    #[arg(long, value_enum, default_value_t = BackendKind::Gist)]
+    backend: BackendKind,
+    /// The gist id to pull from, used when `--backend gist`. Falls back to the active profile's `gist_id` if omitted
+    #[arg(long)]
+    gist_id: Option<String>,
+    /// The github access token to authenticate using, used when `--backend gist`. Falls back to the active profile's `access_token` if omitted
+    #[arg(short = 't', long = "access-token")]
+    github_access_token: Option<String>,
+    /// The URL of the Git repository to pull from, required when `--backend git`
+    #[arg(long)]
+    repo_url: Option<String>,
+    /// The branch to pull from, used when `--backend git`
+    #[arg(long, default_value = "main")]
+    branch: String,
+    /// Local path used to check out the Git repository, used when `--backend git`
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    git_checkout_dir: Option<PathBuf>,
+    /// The subdirectory of the Git repository to read included files from, used when `--backend git`
+    #[arg(long, default_value = ".")]
+    git_subdirectory: PathBuf,
     /// Disable creating files if they're not found locally
     #[arg(short = 'u', long = "update-only", action = clap::ArgAction::SetTrue)]
     update_only: bool,
@@ -26,43 +42,97 @@ pub struct PullOptions {
     dry_run: bool,
 }
 
-pub async fn pull_config(options: PullOptions) {
-    join_all(
-        OctocrabBuilder::new()
-            .user_access_token(options.github_access_token)
-            .build()
-            .unwrap()
-            .gists()
-            .get(options.gist_id)
-            .await
-            .unwrap()
-            .files
-            .iter()
-            .filter(|(file_name, _)| file_name.as_str() != README_FILE)
-            .map(|(file_name, gist_file)| async {
-                let file_contents = gist_file.content.as_ref().unwrap();
-                let mut file_lines = file_contents.lines();
-                let relative_path = &file_lines.next().unwrap_or(file_name.as_str())[3..];
-                let file_contents = file_lines.collect::<Vec<_>>().join("\n");
-                let absolute_path = options.cfg_dir.join(relative_path);
-                let path_name = absolute_path.display();
+impl PullOptions {
+    fn resolve(self, profile: &Profile) -> Result<(PathBuf, Box<dyn Backend>, bool, bool), Error> {
+        let cfg_dir = self.cfg_dir.or_else(|| profile.cfg_dir.clone()).ok_or_else(|| {
+            Error::MissingOption("cfg_dir must be given on the command line or in the active profile".to_owned())
+        })?;
+
+        let backend: Box<dyn Backend> = match self.backend {
+            BackendKind::Gist => {
+                let gist_id = self.gist_id.or_else(|| profile.gist_id.clone()).ok_or_else(|| {
+                    Error::MissingOption(
+                        "gist_id must be given on the command line or in the active profile".to_owned(),
+                    )
+                })?;
+                let github_access_token = match self.github_access_token {
+                    Some(token) => token,
+                    None => {
+                        let token_source = profile.access_token.as_ref().ok_or_else(|| {
+                            Error::MissingOption(
+                                "access-token must be given on the command line or in the active profile"
+                                    .to_owned(),
+                            )
+                        })?;
+                        token_source.resolve()?
+                    }
+                };
+                Box::new(GistBackend::new(gist_id, github_access_token))
+            }
+            BackendKind::Git => {
+                let repo_url = self.repo_url.ok_or_else(|| {
+                    Error::MissingOption(
+                        "repo-url must be given on the command line when --backend git is set".to_owned(),
+                    )
+                })?;
+                let checkout_dir = self
+                    .git_checkout_dir
+                    .unwrap_or_else(|| cfg_dir.join(".cs-config-manager-git"));
+                Box::new(GitBackend::new(
+                    repo_url,
+                    self.branch,
+                    checkout_dir,
+                    self.git_subdirectory,
+                    "cs-config-manager".to_owned(),
+                    "cs-config-manager@localhost".to_owned(),
+                ))
+            }
+        };
+
+        Ok((cfg_dir, backend, self.update_only, self.dry_run))
+    }
+}
 
-                let mut file_write = OpenOptions::new()
-                    .write(true)
-                    .create(!options.update_only)
-                    .open(&absolute_path)
-                    .await
-                    .unwrap();
+pub async fn pull_config(options: PullOptions, profile: &Profile) -> Result<(), Error> {
+    let (cfg_dir, backend, update_only, dry_run) = options.resolve(profile)?;
 
-                if options.dry_run {
-                    let num_bytes = file_contents.len();
-                    info!("skipping writing {num_bytes}B to {path_name} due to --dry-run");
-                    return;
-                }
+    join_all(backend.list().await?.into_iter().map(|remote_file| async {
+        let mut file_lines = remote_file.content.lines();
+        let header_line = file_lines.next().unwrap_or(remote_file.name.as_str());
+        let relative_path = header_line.get(3..).ok_or_else(|| Error::MalformedHeader {
+            name: remote_file.name.clone(),
+        })?;
+        let file_contents = file_lines.collect::<Vec<_>>().join("\n");
+        let absolute_path = cfg_dir.join(relative_path);
+        let path_name = absolute_path.display();
 
-                let written_bytes = file_write.write(file_contents.as_bytes()).await.unwrap();
-                info!("wrote {written_bytes}B to {path_name}");
-            }),
-    )
-    .await;
+        if dry_run {
+            let num_bytes = file_contents.len();
+            info!("skipping writing {num_bytes}B to {path_name} due to --dry-run");
+            return Ok(());
+        }
+
+        let mut file_write = OpenOptions::new()
+            .write(true)
+            .create(!update_only)
+            .open(&absolute_path)
+            .await
+            .map_err(|source| Error::Write {
+                path: absolute_path.clone(),
+                source,
+            })?;
+
+        let written_bytes = file_write
+            .write(file_contents.as_bytes())
+            .await
+            .map_err(|source| Error::Write {
+                path: absolute_path.clone(),
+                source,
+            })?;
+        info!("wrote {written_bytes}B to {path_name}");
+        Ok(())
+    }))
+    .await
+    .into_iter()
+    .collect()
 }
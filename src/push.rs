@@ -1,37 +1,156 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
-use octocrab::OctocrabBuilder;
 use tracing::info;
 
+use crate::backend::{Backend, BackendKind, GistBackend, GitBackend, RemoteFile};
+use crate::config::{Profile, TokenSource};
+use crate::error::Error;
 use crate::README_FILE;
 
 #[derive(Args, Debug, Clone)]
 pub struct PushOptions {
-    /// The `./cfg` directory to run against, used to get relative paths from exec calls to include with the files
+    /// The `./cfg` directory to run against, used to get relative paths from exec calls to include with the files. Falls back to the active profile's `cfg_dir` if omitted
     #[arg(value_name = "CFG_DIR", value_hint = clap::ValueHint::DirPath)]
-    cfg_dir: PathBuf,
-    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files
+    cfg_dir: Option<PathBuf>,
+    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files. Falls back to the active profile's `root_file` if omitted
     #[arg(value_name = "AUTOEXEC.CFG", value_hint = clap::ValueHint::FilePath)]
-    root_file: PathBuf,
-    /// The gist id to publish to
-    #[arg(long, required = true)]
-    gist_id: String,
-    /// The github access token to authenticate using
-    #[arg(short = 't', long = "access-token", required = true)]
-    github_access_token: String,
+    root_file: Option<PathBuf>,
+    /// Which kind of remote to publish to
+    #[arg(long, value_enum, default_value_t = BackendKind::Gist)]
+    backend: BackendKind,
+    /// The gist id to publish to, used when `--backend gist`. Falls back to the active profile's `gist_id` if omitted
+    #[arg(long)]
+    gist_id: Option<String>,
+    /// The github access token to authenticate using, used when `--backend gist`. Falls back to the active profile's `access_token` if omitted
+    #[arg(short = 't', long = "access-token")]
+    github_access_token: Option<String>,
+    /// The URL of the Git repository to publish to, required when `--backend git`
+    #[arg(long)]
+    repo_url: Option<String>,
+    /// The branch to commit and push to, used when `--backend git`
+    #[arg(long, default_value = "main")]
+    branch: String,
+    /// Local path used to check out the Git repository, used when `--backend git`
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    git_checkout_dir: Option<PathBuf>,
+    /// The subdirectory of the Git repository to write included files into, used when `--backend git`
+    #[arg(long, default_value = ".")]
+    git_subdirectory: PathBuf,
+    /// The `name <email>` to attribute sync commits to, used when `--backend git`
+    #[arg(long, default_value = "cs-config-manager <cs-config-manager@localhost>")]
+    commit_author: String,
     /// Whether or not to actually upload file
     #[arg(long, action = clap::ArgAction::SetTrue)]
     dry_run: bool,
 }
 
-pub async fn push_config(options: PushOptions) {
+impl PushOptions {
+    fn resolve(self, profile: &Profile) -> Result<(PathBuf, PathBuf, Box<dyn Backend>, bool), Error> {
+        let cfg_dir = self.cfg_dir.or_else(|| profile.cfg_dir.clone()).ok_or_else(|| {
+            Error::MissingOption("cfg_dir must be given on the command line or in the active profile".to_owned())
+        })?;
+        let root_file = self.root_file.or_else(|| profile.root_file.clone()).ok_or_else(|| {
+            Error::MissingOption("root_file must be given on the command line or in the active profile".to_owned())
+        })?;
+
+        let backend: Box<dyn Backend> = match self.backend {
+            BackendKind::Gist => {
+                let gist_id = self.gist_id.or_else(|| profile.gist_id.clone()).ok_or_else(|| {
+                    Error::MissingOption(
+                        "gist_id must be given on the command line or in the active profile".to_owned(),
+                    )
+                })?;
+                let github_access_token = match self.github_access_token {
+                    Some(token) => token,
+                    None => {
+                        let token_source = profile.access_token.as_ref().ok_or_else(|| {
+                            Error::MissingOption(
+                                "access-token must be given on the command line or in the active profile"
+                                    .to_owned(),
+                            )
+                        })?;
+                        token_source.resolve()?
+                    }
+                };
+                Box::new(GistBackend::new(gist_id, github_access_token))
+            }
+            BackendKind::Git => {
+                let repo_url = self.repo_url.ok_or_else(|| {
+                    Error::MissingOption(
+                        "repo-url must be given on the command line when --backend git is set".to_owned(),
+                    )
+                })?;
+                let (author_name, author_email) = parse_commit_author(&self.commit_author);
+                let checkout_dir = self
+                    .git_checkout_dir
+                    .unwrap_or_else(|| cfg_dir.join(".cs-config-manager-git"));
+                Box::new(GitBackend::new(
+                    repo_url,
+                    self.branch,
+                    checkout_dir,
+                    self.git_subdirectory,
+                    author_name,
+                    author_email,
+                ))
+            }
+        };
+
+        Ok((cfg_dir, root_file, backend, self.dry_run))
+    }
+}
+
+/// Splits a `name <email>` commit author string, falling back to the whole string as the name
+/// (and an empty address) when it isn't in that form.
+pub(crate) fn parse_commit_author(commit_author: &str) -> (String, String) {
+    commit_author
+        .strip_suffix('>')
+        .and_then(|prefix| prefix.split_once(" <"))
+        .map_or_else(
+            || (commit_author.to_owned(), String::new()),
+            |(name, email)| (name.to_owned(), email.to_owned()),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_commit_author;
+
+    #[test]
+    fn splits_name_and_email() {
+        let (name, email) = parse_commit_author("CS Config Manager <cs-config-manager@localhost>");
+        assert_eq!(name, "CS Config Manager");
+        assert_eq!(email, "cs-config-manager@localhost");
+    }
+
+    #[test]
+    fn falls_back_to_the_whole_string_as_the_name() {
+        let (name, email) = parse_commit_author("not-an-email-form");
+        assert_eq!(name, "not-an-email-form");
+        assert_eq!(email, "");
+    }
+}
+
+pub async fn push_config(options: PushOptions, profile: &Profile) -> Result<(), Error> {
+    let (cfg_dir, root_file, backend, dry_run) = options.resolve(profile)?;
+    push_included_files(&cfg_dir, &root_file, backend.as_ref(), dry_run).await
+}
+
+/// Uploads everything reachable from `root_file` to `backend`, deleting remote files that are no
+/// longer locally included. Shared by `push_config` and `watch`'s `--push`, so both stay in sync
+/// on README handling and stale-file cleanup regardless of which backend was selected.
+pub(crate) async fn push_included_files(
+    cfg_dir: &Path,
+    root_file: &Path,
+    backend: &dyn Backend,
+    dry_run: bool,
+) -> Result<(), Error> {
     // The text content to upload for the readme
     let readme_content = format!(
         "# Compiled on {}\n\n",
         chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
     );
-    let included_files = crate::get_included_files(&options.cfg_dir, &options.root_file);
+    let included_files = crate::get_included_files(cfg_dir, root_file)?;
     // Number of included files and the readme
     let num_files = included_files.len() + 1;
     // Total bytes of included files and the readme
@@ -41,46 +160,34 @@ pub async fn push_config(options: PushOptions) {
         .sum::<usize>()
         + readme_content.len();
 
-    if options.dry_run {
-        info!(
-            "skipping uploading {num_files} files ({total_bytes}B) to gist {} due to --dry-run",
-            options.gist_id
-        );
-        return;
+    if dry_run {
+        info!("skipping uploading {num_files} files ({total_bytes}B) due to --dry-run");
+        return Ok(());
     }
 
-    let octocrab = OctocrabBuilder::new()
-        .user_access_token(options.github_access_token)
-        .build()
-        .unwrap();
-
-    // Delete files not found locally
-    let current_gist = octocrab.gists().get(&options.gist_id).await.unwrap();
-    let deleted_files_names = current_gist.files.keys().filter(|deleted_file| {
-        included_files
-            .iter()
-            .any(|i| i.get_file_name() == deleted_file.as_str())
-    });
-    let gist = octocrab.gists().update(options.gist_id);
-    let gist = deleted_files_names.fold(gist, |gist, deleted_file_name| {
-        gist.file(deleted_file_name).delete()
-    });
-
-    // Add or update included files on gist
-    let gist = included_files
+    // Delete remote files not found locally
+    let current_files = backend.list().await?;
+    let deleted_file_names = current_files
         .into_iter()
-        .fold(
-            gist.file(README_FILE).with_content(readme_content),
-            |gist, included| {
-                gist.file(included.get_file_name())
-                    .with_content(included.get_formatted_content())
-            },
-        )
-        .send()
-        .await
-        .unwrap();
-    info!(
-        "uploaded {num_files} files ({total_bytes}B) to {}",
-        gist.html_url
-    );
+        .map(|remote_file| remote_file.name)
+        .filter(|remote_file_name| {
+            !included_files
+                .iter()
+                .any(|included| &included.get_file_name() == remote_file_name)
+        })
+        .collect();
+    backend.delete(deleted_file_names).await?;
+
+    // Add or update included files on the remote
+    let mut remote_files = vec![RemoteFile {
+        name: README_FILE.to_owned(),
+        content: readme_content,
+    }];
+    remote_files.extend(included_files.into_iter().map(|included| RemoteFile {
+        name: included.get_file_name(),
+        content: included.get_formatted_content(),
+    }));
+    backend.upsert(remote_files).await?;
+    info!("uploaded {num_files} files ({total_bytes}B)");
+    Ok(())
 }
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use clap::Args;
+use similar::TextDiff;
+use tracing::info;
+
+use crate::backend::{Backend, BackendKind, GistBackend, GitBackend};
+use crate::config::{Profile, TokenSource};
+use crate::error::Error;
+
+#[derive(Args, Debug, Clone)]
+pub struct StatusOptions {
+    /// The `./cfg` directory to run against, used to get relative paths from exec calls to include with the files. Falls back to the active profile's `cfg_dir` if omitted
+    #[arg(value_name = "CFG_DIR", value_hint = clap::ValueHint::DirPath)]
+    cfg_dir: Option<PathBuf>,
+    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files. Falls back to the active profile's `root_file` if omitted
+    #[arg(value_name = "AUTOEXEC.CFG", value_hint = clap::ValueHint::FilePath)]
+    root_file: Option<PathBuf>,
+    /// Which kind of remote to compare against
+    #[arg(long, value_enum, default_value_t = BackendKind::Gist)]
+    backend: BackendKind,
+    /// The gist id to compare against, used when `--backend gist`. Falls back to the active profile's `gist_id` if omitted
+    #[arg(long)]
+    gist_id: Option<String>,
+    /// The github access token to authenticate using, used when `--backend gist`. Falls back to the active profile's `access_token` if omitted
+    #[arg(short = 't', long = "access-token")]
+    github_access_token: Option<String>,
+    /// The URL of the Git repository to compare against, required when `--backend git`
+    #[arg(long)]
+    repo_url: Option<String>,
+    /// The branch to compare against, used when `--backend git`
+    #[arg(long, default_value = "main")]
+    branch: String,
+    /// Local path used to check out the Git repository, used when `--backend git`
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    git_checkout_dir: Option<PathBuf>,
+    /// The subdirectory of the Git repository to compare against, used when `--backend git`
+    #[arg(long, default_value = ".")]
+    git_subdirectory: PathBuf,
+}
+
+impl StatusOptions {
+    fn resolve(self, profile: &Profile) -> Result<(PathBuf, PathBuf, Box<dyn Backend>), Error> {
+        let cfg_dir = self.cfg_dir.or_else(|| profile.cfg_dir.clone()).ok_or_else(|| {
+            Error::MissingOption("cfg_dir must be given on the command line or in the active profile".to_owned())
+        })?;
+        let root_file = self.root_file.or_else(|| profile.root_file.clone()).ok_or_else(|| {
+            Error::MissingOption("root_file must be given on the command line or in the active profile".to_owned())
+        })?;
+
+        let backend: Box<dyn Backend> = match self.backend {
+            BackendKind::Gist => {
+                let gist_id = self.gist_id.or_else(|| profile.gist_id.clone()).ok_or_else(|| {
+                    Error::MissingOption(
+                        "gist_id must be given on the command line or in the active profile".to_owned(),
+                    )
+                })?;
+                let github_access_token = match self.github_access_token {
+                    Some(token) => token,
+                    None => {
+                        let token_source = profile.access_token.as_ref().ok_or_else(|| {
+                            Error::MissingOption(
+                                "access-token must be given on the command line or in the active profile"
+                                    .to_owned(),
+                            )
+                        })?;
+                        token_source.resolve()?
+                    }
+                };
+                Box::new(GistBackend::new(gist_id, github_access_token))
+            }
+            BackendKind::Git => {
+                let repo_url = self.repo_url.ok_or_else(|| {
+                    Error::MissingOption(
+                        "repo-url must be given on the command line when --backend git is set".to_owned(),
+                    )
+                })?;
+                let checkout_dir = self
+                    .git_checkout_dir
+                    .unwrap_or_else(|| cfg_dir.join(".cs-config-manager-git"));
+                Box::new(GitBackend::new(
+                    repo_url,
+                    self.branch,
+                    checkout_dir,
+                    self.git_subdirectory,
+                    "cs-config-manager".to_owned(),
+                    "cs-config-manager@localhost".to_owned(),
+                ))
+            }
+        };
+
+        Ok((cfg_dir, root_file, backend))
+    }
+}
+
+/// Nothing is persisted between runs to compare the local and remote copies against a common
+/// base, so a changed file can only be reported as `Modified` (differs on both sides, direction
+/// unknown) rather than split into "locally modified" vs "remote-modified" — telling those apart
+/// would require tracking a base snapshot, which this tool doesn't do.
+enum FileStatus {
+    /// Present on both sides with identical content
+    Unchanged,
+    /// Present on both sides with different content (push would overwrite the remote, pull would overwrite local)
+    Modified,
+    /// Only found locally (would be uploaded on push, left untouched by pull)
+    OnlyLocal,
+    /// Only found on the remote (would be deleted on push, created on pull)
+    OnlyRemote,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Unchanged => "unchanged",
+            Self::Modified => "modified",
+            Self::OnlyLocal => "only-local",
+            Self::OnlyRemote => "only-remote",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Strips the `// <relative path>` header line that `push` prepends, returning the remainder of
+/// `content` verbatim (trailing newline included) so it compares equal to the untouched local
+/// file contents instead of always registering as modified.
+fn strip_header(content: &str) -> String {
+    content
+        .split_once('\n')
+        .map_or_else(String::new, |(_, rest)| rest.to_owned())
+}
+
+pub async fn status_config(options: StatusOptions, profile: &Profile) -> Result<(), Error> {
+    let (cfg_dir, root_file, backend) = options.resolve(profile)?;
+
+    let included_files = crate::get_included_files(&cfg_dir, &root_file)?;
+    let mut remote_file_contents: HashMap<String, String> = backend
+        .list()
+        .await?
+        .into_iter()
+        .map(|remote_file| (remote_file.name, strip_header(&remote_file.content)))
+        .collect();
+
+    for included in &included_files {
+        let file_name = included.get_file_name();
+        match remote_file_contents.remove(&file_name) {
+            None => info!("{file_name}: {}", FileStatus::OnlyLocal),
+            Some(remote_content) if remote_content == included.file_contents => {
+                info!("{file_name}: {}", FileStatus::Unchanged);
+            }
+            Some(remote_content) => {
+                info!("{file_name}: {}", FileStatus::Modified);
+                print_unified_diff(&file_name, &remote_content, &included.file_contents);
+            }
+        }
+    }
+    for only_remote_file_name in remote_file_contents.into_keys() {
+        info!("{only_remote_file_name}: {}", FileStatus::OnlyRemote);
+    }
+    Ok(())
+}
+
+fn print_unified_diff(file_name: &str, remote_content: &str, local_content: &str) {
+    let diff = TextDiff::from_lines(remote_content, local_content);
+    let unified_diff = diff
+        .unified_diff()
+        .header(&format!("remote/{file_name}"), &format!("local/{file_name}"))
+        .to_string();
+    info!("\n{unified_diff}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_header;
+
+    #[test]
+    fn strip_header_round_trips_a_trailing_newline() {
+        let local_content = "bind e use\nbind r reload\n";
+        let pushed = format!("// autoexec.cfg\n{local_content}");
+        assert_eq!(strip_header(&pushed), local_content);
+    }
+
+    #[test]
+    fn strip_header_round_trips_no_trailing_newline() {
+        let local_content = "bind e use\nbind r reload";
+        let pushed = format!("// autoexec.cfg\n{local_content}");
+        assert_eq!(strip_header(&pushed), local_content);
+    }
+}
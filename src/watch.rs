@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use clap::Args;
+use notify::{RecursiveMode, Watcher};
+use tracing::{error, info, warn};
+
+use crate::backend::{Backend, BackendKind, GistBackend, GitBackend};
+use crate::compile::{self, CompileOptions};
+use crate::config::{Profile, TokenSource};
+use crate::error::Error;
+use crate::push;
+
+#[derive(Args, Debug, Clone)]
+pub struct WatchOptions {
+    /// The `./cfg` directory to run against, used to get relative paths from exec calls to concatenate the files. Falls back to the active profile's `cfg_dir` if omitted
+    #[arg(value_name = "CFG_DIR", value_hint = clap::ValueHint::DirPath)]
+    cfg_dir: Option<PathBuf>,
+    /// The relative path of the root cfg (ie. `autoexec.cfg`) file to run against, following exec calls to concatenate the files. Falls back to the active profile's `root_file` if omitted
+    #[arg(value_name = "AUTOEXEC.CFG", value_hint = clap::ValueHint::FilePath)]
+    root_file: Option<PathBuf>,
+    /// How long to wait after the most recent file change before recompiling, coalescing bursts of saves into a single rebuild
+    #[arg(long, default_value_t = 250)]
+    debounce_ms: u64,
+    /// Push the freshly compiled config to the remote after each successful rebuild
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    push: bool,
+    /// Which kind of remote to push to, used when `--push` is set
+    #[arg(long, value_enum, default_value_t = BackendKind::Gist)]
+    backend: BackendKind,
+    /// The gist id to publish to, used when `--push` is set and `--backend gist`. Falls back to the active profile's `gist_id` if omitted
+    #[arg(long)]
+    gist_id: Option<String>,
+    /// The github access token to authenticate using, used when `--push` is set and `--backend gist`. Falls back to the active profile's `access_token` if omitted
+    #[arg(short = 't', long = "access-token")]
+    github_access_token: Option<String>,
+    /// The URL of the Git repository to push to, required when `--push` is set and `--backend git`
+    #[arg(long)]
+    repo_url: Option<String>,
+    /// The branch to commit and push to, used when `--push` is set and `--backend git`
+    #[arg(long, default_value = "main")]
+    branch: String,
+    /// Local path used to check out the Git repository, used when `--push` is set and `--backend git`
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    git_checkout_dir: Option<PathBuf>,
+    /// The subdirectory of the Git repository to write included files into, used when `--push` is set and `--backend git`
+    #[arg(long, default_value = ".")]
+    git_subdirectory: PathBuf,
+    /// The `name <email>` to attribute sync commits to, used when `--push` is set and `--backend git`
+    #[arg(long, default_value = "cs-config-manager <cs-config-manager@localhost>")]
+    commit_author: String,
+}
+
+impl WatchOptions {
+    #[allow(clippy::type_complexity)]
+    fn resolve(
+        self,
+        profile: &Profile,
+    ) -> Result<(PathBuf, PathBuf, Duration, Option<Box<dyn Backend + Send>>), Error> {
+        let cfg_dir = self.cfg_dir.or_else(|| profile.cfg_dir.clone()).ok_or_else(|| {
+            Error::MissingOption("cfg_dir must be given on the command line or in the active profile".to_owned())
+        })?;
+        let root_file = self.root_file.or_else(|| profile.root_file.clone()).ok_or_else(|| {
+            Error::MissingOption("root_file must be given on the command line or in the active profile".to_owned())
+        })?;
+
+        let push_backend = if self.push {
+            let backend: Box<dyn Backend + Send> = match self.backend {
+                BackendKind::Gist => {
+                    let gist_id = self.gist_id.or_else(|| profile.gist_id.clone()).ok_or_else(|| {
+                        Error::MissingOption(
+                            "gist_id must be given on the command line or in the active profile when --push is set"
+                                .to_owned(),
+                        )
+                    })?;
+                    let github_access_token = match self.github_access_token {
+                        Some(token) => token,
+                        None => {
+                            let token_source = profile.access_token.as_ref().ok_or_else(|| {
+                                Error::MissingOption(
+                                    "access-token must be given on the command line or in the active profile when \
+                                     --push is set"
+                                        .to_owned(),
+                                )
+                            })?;
+                            token_source.resolve()?
+                        }
+                    };
+                    Box::new(GistBackend::new(gist_id, github_access_token))
+                }
+                BackendKind::Git => {
+                    let repo_url = self.repo_url.ok_or_else(|| {
+                        Error::MissingOption(
+                            "repo-url must be given on the command line when --push is set and --backend git is \
+                             set"
+                                .to_owned(),
+                        )
+                    })?;
+                    let (author_name, author_email) = push::parse_commit_author(&self.commit_author);
+                    let checkout_dir = self
+                        .git_checkout_dir
+                        .unwrap_or_else(|| cfg_dir.join(".cs-config-manager-git"));
+                    Box::new(GitBackend::new(
+                        repo_url,
+                        self.branch,
+                        checkout_dir,
+                        self.git_subdirectory,
+                        author_name,
+                        author_email,
+                    ))
+                }
+            };
+            Some(backend)
+        } else {
+            None
+        };
+
+        Ok((cfg_dir, root_file, Duration::from_millis(self.debounce_ms), push_backend))
+    }
+}
+
+/// Canonicalized paths of every file currently reachable from `root_file`, used to filter
+/// filesystem events down to ones that actually matter to the compiled output.
+fn watched_file_set(cfg_dir: &Path, root_file: &Path) -> Result<HashSet<PathBuf>, Error> {
+    Ok(crate::get_included_files(cfg_dir, root_file)?
+        .into_iter()
+        .filter_map(|included| cfg_dir.join(included.relative_file_path).canonicalize().ok())
+        .collect())
+}
+
+pub async fn watch_config(options: WatchOptions, profile: &Profile) -> Result<(), Error> {
+    let (cfg_dir, root_file, debounce, push_backend) = options.resolve(profile)?;
+    let handle = tokio::runtime::Handle::current();
+
+    tokio::task::spawn_blocking(move || -> Result<(), Error> {
+        let (tx, rx) = channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(&cfg_dir, RecursiveMode::Recursive)?;
+
+        let mut watched_files = watched_file_set(&cfg_dir, &root_file)?;
+        info!(
+            "watching {} included file(s) under {}",
+            watched_files.len(),
+            cfg_dir.display()
+        );
+
+        while let Ok(first_event) = rx.recv() {
+            let event_touches_watched_file = |event: &notify::Result<notify::Event>| match event {
+                Ok(event) => event
+                    .paths
+                    .iter()
+                    .filter_map(|path| path.canonicalize().ok())
+                    .any(|path| watched_files.contains(&path)),
+                Err(_) => true,
+            };
+
+            let mut touches_watched_file = event_touches_watched_file(&first_event);
+            // Coalesce any further events arriving within the debounce window into one rebuild,
+            // without losing track of whether any of them (not just the first) touched a
+            // watched file
+            while let Ok(event) = rx.recv_timeout(debounce) {
+                touches_watched_file |= event_touches_watched_file(&event);
+            }
+            if !touches_watched_file {
+                continue;
+            }
+
+            if let Err(err) = compile::compile_and_write(
+                CompileOptions::new(cfg_dir.clone(), root_file.clone(), false),
+                &Profile::default(),
+            ) {
+                error!("failed to recompile {}: {err}", root_file.display());
+                continue;
+            }
+            info!("recompiled {} after detecting a change", root_file.display());
+
+            if let Some(backend) = &push_backend {
+                let push_result =
+                    handle.block_on(push::push_included_files(&cfg_dir, &root_file, backend.as_ref(), false));
+                if let Err(err) = push_result {
+                    error!("failed to push {}: {err}", root_file.display());
+                    continue;
+                }
+            }
+
+            // New exec lines may have been added (or removed) by the rebuild, so recompute
+            watched_files = match watched_file_set(&cfg_dir, &root_file) {
+                Ok(watched_files) => watched_files,
+                Err(err) => {
+                    error!("failed to recompute watched files: {err}");
+                    continue;
+                }
+            };
+        }
+
+        warn!("file watcher channel closed, stopping watch");
+        Ok(())
+    })
+    .await
+    .expect("watch task panicked")
+}